@@ -6,6 +6,8 @@
 //! [XDG standard](https://wiki.archlinux.org/index.php/XDG_Base_Directory_support)
 //! in user-targeted applications.
 //!
+//! Config, cache, data, runtime, and state directories are all covered.
+//!
 //! This package uses std::env as the primary form
 //! of defining the folder strucutre, but falls back
 //! to the common locations since some
@@ -17,12 +19,10 @@
 //! ```
 //! # #[macro_use] extern crate xdg_user_macros;
 //! # use std::path::PathBuf;
-//! # use std::env::{self, home_dir};
+//! # use std::env;
 //! # fn main() {
-//! let path = xdg_data_home!("my-awesome-app");
-//! let mut expected = home_dir().unwrap();
-//! expected.push(".local/share/my-awesome-app");
-//! assert_eq!(path, expected)
+//! let path: PathBuf = xdg_data_home!("my-awesome-app");
+//! assert!(path.ends_with("my-awesome-app"));
 //! # }
 //! ```
 //!
@@ -31,30 +31,227 @@
 //! the folders associated with the returned PathBuf
 //! from each macro. Folder presence checks _should_ be
 //! handled elsewhere in the application.
+//!
+//! This crate builds on Linux, macOS, and Windows. On Windows there is
+//! no `/run/user/<uid>` and no native equivalent of `$HOME`, so the
+//! home directory is read from `%USERPROFILE%` and the config/cache/data/state
+//! homes fall back to `%APPDATA%`/`%LOCALAPPDATA%` when unset, matching
+//! what native Windows applications expect.
+//!
+//! Every macro that reads the process environment (the `*_home!` macros,
+//! `xdg_runtime_dir!`, `xdg_runtime_dir_checked!`, `xdg_data_dirs!`,
+//! `xdg_config_dirs!`, `xdg_find_config!`, `xdg_find_data!`, and the
+//! user-directory macros like `xdg_desktop_dir!`) has a `*_from_env!`
+//! sibling that takes an explicit `Fn(&str) -> Option<OsString>` lookup
+//! (and, where relevant, an explicit home directory) instead of reading
+//! the real process environment, for hermetic tests.
 
-#![allow(unused_macros, unused_imports, dead_code)]
+#![allow(unused_macros, unused_imports, dead_code, unused_mut, unused_variables)]
 
+#[cfg(unix)]
 extern crate libc;
 
+#[cfg(unix)]
 #[doc(no_inline)]
 use libc::getuid;
+#[cfg(unix)]
 #[doc(no_inline)]
 use libc::uid_t;
-use std::env::{self, home_dir};
+use std::env;
 use std::path::PathBuf;
 
+/// Returns the user's home directory: `$HOME` on Unix, `%USERPROFILE%`
+/// on Windows.
+#[doc(hidden)]
+pub fn __xdg_home_dir() -> PathBuf {
+    #[cfg(windows)]
+    let var = "USERPROFILE";
+    #[cfg(not(windows))]
+    let var = "HOME";
+
+    PathBuf::from(env::var_os(var).expect("neither $HOME nor %USERPROFILE% is set"))
+}
+
+/// Returns the platform default for the config home when
+/// `$XDG_CONFIG_HOME` is unset, using `lookup` for any env var reads
+/// (e.g. `%APPDATA%` on Windows) and `home` as the user's home directory:
+/// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS,
+/// `~/.config` elsewhere.
+#[doc(hidden)]
+pub fn __xdg_default_config_home_with<F: Fn(&str) -> Option<std::ffi::OsString>>(
+    lookup: F,
+    home: PathBuf,
+) -> PathBuf {
+    #[cfg(windows)]
+    {
+        lookup("APPDATA").map(PathBuf::from).unwrap_or_else(|| {
+            let mut path = home;
+            path.push("AppData/Roaming");
+            path
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut path = home;
+        path.push("Library/Application Support");
+        path
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let mut path = home;
+        path.push(".config");
+        path
+    }
+}
+
+/// Returns the platform default for the cache home when
+/// `$XDG_CACHE_HOME` is unset, using `lookup` for any env var reads
+/// (e.g. `%LOCALAPPDATA%` on Windows) and `home` as the user's home
+/// directory: `%LOCALAPPDATA%` on Windows, `~/Library/Caches` on macOS,
+/// `~/.cache` elsewhere.
+#[doc(hidden)]
+pub fn __xdg_default_cache_home_with<F: Fn(&str) -> Option<std::ffi::OsString>>(
+    lookup: F,
+    home: PathBuf,
+) -> PathBuf {
+    #[cfg(windows)]
+    {
+        lookup("LOCALAPPDATA").map(PathBuf::from).unwrap_or_else(|| {
+            let mut path = home;
+            path.push("AppData/Local");
+            path
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut path = home;
+        path.push("Library/Caches");
+        path
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let mut path = home;
+        path.push(".cache");
+        path
+    }
+}
+
+/// Returns the platform default for the data home when
+/// `$XDG_DATA_HOME` is unset, using `lookup` for any env var reads
+/// (e.g. `%APPDATA%` on Windows) and `home` as the user's home directory:
+/// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS,
+/// `~/.local/share` elsewhere.
+#[doc(hidden)]
+pub fn __xdg_default_data_home_with<F: Fn(&str) -> Option<std::ffi::OsString>>(
+    lookup: F,
+    home: PathBuf,
+) -> PathBuf {
+    #[cfg(windows)]
+    {
+        lookup("APPDATA").map(PathBuf::from).unwrap_or_else(|| {
+            let mut path = home;
+            path.push("AppData/Roaming");
+            path
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut path = home;
+        path.push("Library/Application Support");
+        path
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let mut path = home;
+        path.push(".local/share");
+        path
+    }
+}
+
+/// Returns the platform default for the state home when
+/// `$XDG_STATE_HOME` is unset, using `lookup` for any env var reads
+/// (e.g. `%LOCALAPPDATA%` on Windows) and `home` as the user's home
+/// directory: `%LOCALAPPDATA%\State` on Windows,
+/// `~/Library/Application Support/State` on macOS, `~/.local/state`
+/// elsewhere.
+#[doc(hidden)]
+pub fn __xdg_default_state_home_with<F: Fn(&str) -> Option<std::ffi::OsString>>(
+    lookup: F,
+    home: PathBuf,
+) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let mut path = lookup("LOCALAPPDATA").map(PathBuf::from).unwrap_or_else(|| {
+            let mut path = home;
+            path.push("AppData/Local");
+            path
+        });
+        path.push("State");
+        path
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut path = home;
+        path.push("Library/Application Support/State");
+        path
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let mut path = home;
+        path.push(".local/state");
+        path
+    }
+}
+
+/// Returns the platform default for the config home using the real
+/// process environment and home directory.
+#[doc(hidden)]
+pub fn __xdg_default_config_home() -> PathBuf {
+    __xdg_default_config_home_with(|key: &str| env::var_os(key), __xdg_home_dir())
+}
+
+/// Returns the platform default for the cache home using the real
+/// process environment and home directory.
+#[doc(hidden)]
+pub fn __xdg_default_cache_home() -> PathBuf {
+    __xdg_default_cache_home_with(|key: &str| env::var_os(key), __xdg_home_dir())
+}
+
+/// Returns the platform default for the data home using the real
+/// process environment and home directory.
+#[doc(hidden)]
+pub fn __xdg_default_data_home() -> PathBuf {
+    __xdg_default_data_home_with(|key: &str| env::var_os(key), __xdg_home_dir())
+}
+
+/// Returns the platform default for the state home using the real
+/// process environment and home directory.
+#[doc(hidden)]
+pub fn __xdg_default_state_home() -> PathBuf {
+    __xdg_default_state_home_with(|key: &str| env::var_os(key), __xdg_home_dir())
+}
+
 /// Returns a PathBuf pointing to what should be defined
 /// as the $XDG_CONFIG_HOME environment variable.
 #[macro_export]
 macro_rules! xdg_config_home {
     ($($x: expr),*) => {{
-        let mut path = match env::var_os("XDG_CONFIG_HOME") {
+        $crate::xdg_config_home_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_config_home!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment. Lets callers feed a synthetic
+/// environment for hermetic tests.
+#[macro_export]
+macro_rules! xdg_config_home_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let home: PathBuf = $home;
+        let mut path: PathBuf = match lookup("XDG_CONFIG_HOME") {
             Some(val) => PathBuf::from(val),
-            None => {
-                let mut path = home_dir().unwrap();
-                path.push(".config");
-                path
-            }
+            None => $crate::__xdg_default_config_home_with(&lookup, home),
         };
         $(
             path.push($x);
@@ -63,18 +260,74 @@ macro_rules! xdg_config_home {
     }};
 }
 
+/// Resolves one of the user directories (Desktop, Downloads, ...) defined
+/// in `<XDG_CONFIG_HOME>/user-dirs.dirs`, falling back to
+/// `<home>/<fallback>` when the file or the key is absent.
+#[doc(hidden)]
+pub fn __xdg_user_dir_with<F: Fn(&str) -> Option<std::ffi::OsString>>(
+    lookup: F,
+    home: PathBuf,
+    key: &str,
+    fallback: &str,
+) -> PathBuf {
+    let user_dirs_file = xdg_config_home_from_env!(&lookup, home.clone(), "user-dirs.dirs");
+    if let Ok(contents) = std::fs::read_to_string(&user_dirs_file) {
+        let prefix = format!("{}=", key);
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(value) = line.strip_prefix(&prefix) {
+                let value = value.trim().trim_matches('"');
+                if let Some(rest) = value.strip_prefix("${HOME}") {
+                    let mut path = home;
+                    path.push(rest.trim_start_matches('/'));
+                    return path;
+                }
+                if let Some(rest) = value.strip_prefix("$HOME") {
+                    let mut path = home;
+                    path.push(rest.trim_start_matches('/'));
+                    return path;
+                }
+                return PathBuf::from(value);
+            }
+        }
+    }
+    let mut path = home;
+    path.push(fallback);
+    path
+}
+
+/// Returns a PathBuf pointing to a well-known user directory (e.g.
+/// Desktop, Downloads), reading `user-dirs.dirs` from the real process
+/// environment and home directory.
+#[doc(hidden)]
+pub fn __xdg_user_dir(key: &str, fallback: &str) -> PathBuf {
+    __xdg_user_dir_with(|key: &str| env::var_os(key), __xdg_home_dir(), key, fallback)
+}
+
 /// Returns a PathBuf pointing to what should be defined
 /// as the $XDG_CACHE_HOME environment variable.
 #[macro_export]
 macro_rules! xdg_cache_home {
     ($($x: expr),*) => {{
-        let mut path = match env::var_os("XDG_CACHE_HOME") {
+        $crate::xdg_cache_home_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_cache_home!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment. Lets callers feed a synthetic
+/// environment for hermetic tests.
+#[macro_export]
+macro_rules! xdg_cache_home_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let home: PathBuf = $home;
+        let mut path: PathBuf = match lookup("XDG_CACHE_HOME") {
             Some(val) => PathBuf::from(val),
-            None => {
-                let mut path = home_dir().unwrap();
-                path.push(".cache");
-                path
-            }
+            None => $crate::__xdg_default_cache_home_with(&lookup, home),
         };
         $(
             path.push($x);
@@ -88,13 +341,51 @@ macro_rules! xdg_cache_home {
 #[macro_export]
 macro_rules! xdg_data_home {
     ($($x: expr),*) => {{
-        let mut path = match env::var_os("XDG_DATA_HOME") {
+        $crate::xdg_data_home_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_data_home!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment. Lets callers feed a synthetic
+/// environment for hermetic tests.
+#[macro_export]
+macro_rules! xdg_data_home_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let home: PathBuf = $home;
+        let mut path: PathBuf = match lookup("XDG_DATA_HOME") {
             Some(val) => PathBuf::from(val),
-            None => {
-                let mut path = home_dir().unwrap();
-                path.push(".local/share");
-                path
-            }
+            None => $crate::__xdg_default_data_home_with(&lookup, home),
+        };
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to what should be defined
+/// as the $XDG_STATE_HOME environment variable.
+#[macro_export]
+macro_rules! xdg_state_home {
+    ($($x: expr),*) => {{
+        $crate::xdg_state_home_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_state_home!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment. Lets callers feed a synthetic
+/// environment for hermetic tests.
+#[macro_export]
+macro_rules! xdg_state_home_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let home: PathBuf = $home;
+        let mut path: PathBuf = match lookup("XDG_STATE_HOME") {
+            Some(val) => PathBuf::from(val),
+            None => $crate::__xdg_default_state_home_with(&lookup, home),
         };
         $(
             path.push($x);
@@ -111,15 +402,420 @@ macro_rules! xdg_data_home {
 #[macro_export]
 macro_rules! xdg_runtime_dir{
     ($($x: expr),*) => {{
-        let mut path = match env::var_os("XDG_RUNTIME_DIR") {
+        $crate::xdg_runtime_dir_from_env!(
+            |key: &str| env::var_os(key),
+            {
+                #[cfg(unix)]
+                {
+                    let run_dir = "/run/user";
+                    let uid: uid_t = unsafe { getuid() };
+                    PathBuf::from(format!("{}/{}", run_dir, uid))
+                }
+                #[cfg(not(unix))]
+                {
+                    env::temp_dir()
+                }
+            }
+            $(, $x)*
+        )
+    }};
+}
+
+/// Same as [`xdg_runtime_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit fallback directory instead of
+/// reading the real process environment and calling `getuid()`, for
+/// hermetic tests.
+#[macro_export]
+macro_rules! xdg_runtime_dir_from_env {
+    ($lookup: expr, $fallback: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let mut path: PathBuf = match lookup("XDG_RUNTIME_DIR") {
             Some(val) => PathBuf::from(val),
-            None => {
-                let run_dir = "/run/user";
-                let uid: uid_t = unsafe { getuid() };
-                let path = PathBuf::from(format!("{}/{}", run_dir, uid));
-                path
+            None => $fallback,
+        };
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns the path from [`xdg_runtime_dir!`], but only if it passes the
+/// spec's safety requirements: the directory must exist, be owned by the
+/// calling user, and be mode `0700`. Yields `None` instead of handing back
+/// a path an application shouldn't trust with sockets or pipes.
+///
+/// The base `$XDG_RUNTIME_DIR` is validated *before* any `$x` subpath
+/// segments are appended, so `xdg_runtime_dir_checked!("myapp.sock")`
+/// checks the runtime directory itself, not a socket file that doesn't
+/// exist yet.
+#[macro_export]
+macro_rules! xdg_runtime_dir_checked {
+    ($($x: expr),*) => {{
+        $crate::xdg_runtime_dir_checked_from_env!(
+            |key: &str| env::var_os(key),
+            {
+                #[cfg(unix)]
+                {
+                    let run_dir = "/run/user";
+                    let uid: uid_t = unsafe { getuid() };
+                    PathBuf::from(format!("{}/{}", run_dir, uid))
+                }
+                #[cfg(not(unix))]
+                {
+                    env::temp_dir()
+                }
             }
+            $(, $x)*
+        )
+    }};
+}
+
+/// Same as [`xdg_runtime_dir_checked!`], but takes a
+/// `Fn(&str) -> Option<OsString>` for variable lookups and an explicit
+/// fallback directory instead of reading the real process environment and
+/// calling `getuid()`, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_runtime_dir_checked_from_env {
+    ($lookup: expr, $fallback: expr $(, $x: expr)*) => {{
+        let base: PathBuf = $crate::xdg_runtime_dir_from_env!($lookup, $fallback);
+        #[cfg(unix)]
+        let validated = {
+            use std::os::unix::fs::MetadataExt;
+            match std::fs::metadata(&base) {
+                Ok(meta) if meta.is_dir()
+                    && meta.uid() == unsafe { getuid() }
+                    && meta.mode() & 0o777 == 0o700 =>
+                {
+                    Some(base)
+                }
+                _ => None,
+            }
+        };
+        #[cfg(not(unix))]
+        let validated = if base.is_dir() { Some(base) } else { None };
+
+        validated.map(|mut path: PathBuf| {
+            $(
+                path.push($x);
+            )*
+            path
+        })
+    }};
+}
+
+/// Returns a Vec<PathBuf> for the preference-ordered search path
+/// that should be defined as the $XDG_DATA_DIRS environment variable.
+#[macro_export]
+macro_rules! xdg_data_dirs {
+    ($($x: expr),*) => {{
+        $crate::xdg_data_dirs_from_env!(|key: &str| env::var_os(key) $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_data_dirs!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups instead of reading the real process environment,
+/// for hermetic tests.
+#[macro_export]
+macro_rules! xdg_data_dirs_from_env {
+    ($lookup: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let raw = match lookup("XDG_DATA_DIRS") {
+            Some(val) if !val.is_empty() => val,
+            _ => std::ffi::OsString::from("/usr/local/share:/usr/share"),
         };
+        let segments: Vec<PathBuf> = vec![$(PathBuf::from($x)),*];
+        // The spec mandates `:`-separated entries regardless of host OS,
+        // unlike `env::split_paths`, which splits on `;` on Windows.
+        raw.to_string_lossy()
+            .split(':')
+            .map(|entry| {
+                let mut path = PathBuf::from(entry);
+                for segment in &segments {
+                    path.push(segment);
+                }
+                path
+            })
+            .collect::<Vec<PathBuf>>()
+    }};
+}
+
+/// Returns a Vec<PathBuf> for the preference-ordered search path
+/// that should be defined as the $XDG_CONFIG_DIRS environment variable.
+#[macro_export]
+macro_rules! xdg_config_dirs {
+    ($($x: expr),*) => {{
+        $crate::xdg_config_dirs_from_env!(|key: &str| env::var_os(key) $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_config_dirs!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups instead of reading the real process environment,
+/// for hermetic tests.
+#[macro_export]
+macro_rules! xdg_config_dirs_from_env {
+    ($lookup: expr $(, $x: expr)*) => {{
+        let lookup = $lookup;
+        let raw = match lookup("XDG_CONFIG_DIRS") {
+            Some(val) if !val.is_empty() => val,
+            _ => std::ffi::OsString::from("/etc/xdg"),
+        };
+        let segments: Vec<PathBuf> = vec![$(PathBuf::from($x)),*];
+        // The spec mandates `:`-separated entries regardless of host OS,
+        // unlike `env::split_paths`, which splits on `;` on Windows.
+        raw.to_string_lossy()
+            .split(':')
+            .map(|entry| {
+                let mut path = PathBuf::from(entry);
+                for segment in &segments {
+                    path.push(segment);
+                }
+                path
+            })
+            .collect::<Vec<PathBuf>>()
+    }};
+}
+
+/// Searches $XDG_CONFIG_HOME followed by each entry of $XDG_CONFIG_DIRS,
+/// in priority order, and returns the first path that exists on disk.
+///
+/// Unlike the other macros, this one touches the filesystem.
+#[macro_export]
+macro_rules! xdg_find_config {
+    ($x: expr) => {{
+        $crate::xdg_find_config_from_env!(
+            |key: &str| env::var_os(key),
+            $crate::__xdg_home_dir(),
+            $x
+        )
+    }};
+}
+
+/// Same as [`xdg_find_config!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+///
+/// Still touches the filesystem to check which candidate exists.
+#[macro_export]
+macro_rules! xdg_find_config_from_env {
+    ($lookup: expr, $home: expr, $x: expr) => {{
+        let lookup = $lookup;
+        let home: PathBuf = $home;
+        let mut candidates = vec![$crate::xdg_config_home_from_env!(&lookup, home, $x)];
+        candidates.extend($crate::xdg_config_dirs_from_env!(&lookup, $x));
+        candidates.into_iter().find(|path| path.exists())
+    }};
+}
+
+/// Searches $XDG_DATA_HOME followed by each entry of $XDG_DATA_DIRS,
+/// in priority order, and returns the first path that exists on disk.
+///
+/// Unlike the other macros, this one touches the filesystem.
+#[macro_export]
+macro_rules! xdg_find_data {
+    ($x: expr) => {{
+        $crate::xdg_find_data_from_env!(
+            |key: &str| env::var_os(key),
+            $crate::__xdg_home_dir(),
+            $x
+        )
+    }};
+}
+
+/// Same as [`xdg_find_data!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+///
+/// Still touches the filesystem to check which candidate exists.
+#[macro_export]
+macro_rules! xdg_find_data_from_env {
+    ($lookup: expr, $home: expr, $x: expr) => {{
+        let lookup = $lookup;
+        let home: PathBuf = $home;
+        let mut candidates = vec![$crate::xdg_data_home_from_env!(&lookup, home, $x)];
+        candidates.extend($crate::xdg_data_dirs_from_env!(&lookup, $x));
+        candidates.into_iter().find(|path| path.exists())
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Desktop directory, as defined
+/// by `XDG_DESKTOP_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_desktop_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_desktop_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_desktop_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_desktop_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_DESKTOP_DIR", "Desktop");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Downloads directory, as
+/// defined by `XDG_DOWNLOAD_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_download_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_download_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_download_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_download_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_DOWNLOAD_DIR", "Downloads");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Documents directory, as
+/// defined by `XDG_DOCUMENTS_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_documents_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_documents_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_documents_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_documents_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_DOCUMENTS_DIR", "Documents");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Music directory, as defined
+/// by `XDG_MUSIC_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_music_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_music_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_music_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_music_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_MUSIC_DIR", "Music");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Pictures directory, as
+/// defined by `XDG_PICTURES_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_pictures_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_pictures_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_pictures_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_pictures_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_PICTURES_DIR", "Pictures");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Videos directory, as defined
+/// by `XDG_VIDEOS_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_videos_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_videos_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_videos_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_videos_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_VIDEOS_DIR", "Videos");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Templates directory, as
+/// defined by `XDG_TEMPLATES_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_templates_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_templates_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_templates_dir!`], but takes a `Fn(&str) -> Option<OsString>`
+/// for variable lookups and an explicit home directory instead of reading
+/// the real process environment, for hermetic tests.
+#[macro_export]
+macro_rules! xdg_templates_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_TEMPLATES_DIR", "Templates");
+        $(
+            path.push($x);
+        )*
+        path
+    }};
+}
+
+/// Returns a PathBuf pointing to the user's Public/Share directory, as
+/// defined by `XDG_PUBLICSHARE_DIR` in `user-dirs.dirs`.
+#[macro_export]
+macro_rules! xdg_public_share_dir {
+    ($($x: expr),*) => {{
+        $crate::xdg_public_share_dir_from_env!(|key: &str| env::var_os(key), $crate::__xdg_home_dir() $(, $x)*)
+    }};
+}
+
+/// Same as [`xdg_public_share_dir!`], but takes a
+/// `Fn(&str) -> Option<OsString>` for variable lookups and an explicit
+/// home directory instead of reading the real process environment, for
+/// hermetic tests.
+#[macro_export]
+macro_rules! xdg_public_share_dir_from_env {
+    ($lookup: expr, $home: expr $(, $x: expr)*) => {{
+        let mut path = $crate::__xdg_user_dir_with($lookup, $home, "XDG_PUBLICSHARE_DIR", "Public");
         $(
             path.push($x);
         )*
@@ -130,50 +826,312 @@ macro_rules! xdg_runtime_dir{
 #[cfg(test)]
 mod test {
     use super::*;
+    #[cfg(unix)]
     use libc::getuid;
-    use libc::uid_t;
-    use std::env::{self, home_dir};
+    use std::env;
     use std::path::PathBuf;
 
     #[test]
     pub fn test_xdg_data_home() {
-        let data_home: PathBuf = xdg_data_home!("test");
-        let expected = PathBuf::from(format!(
-            "{}/{}",
-            home_dir().unwrap().to_str().unwrap(),
-            ".local/share/test",
-        ));
-        assert_eq!(expected, data_home)
+        // Uses the `_from_env!` sibling with an empty lookup/synthetic
+        // home so this can't fail on a machine where $XDG_DATA_HOME
+        // happens to already be set.
+        let lookup = |_: &str| None::<std::ffi::OsString>;
+        let data_home: PathBuf =
+            xdg_data_home_from_env!(lookup, PathBuf::from("/synthetic/home"), "test");
+        assert_eq!(PathBuf::from("/synthetic/home/.local/share/test"), data_home)
     }
 
+    #[cfg(unix)]
     #[test]
     pub fn test_xdg_runtime_dir() {
-        let runtime_dir: PathBuf = xdg_runtime_dir!();
-        let uid: uid_t = unsafe { getuid() };
-        let expected = PathBuf::from(format!("{}/{}", "/run/user", uid));
-        assert_eq!(expected, runtime_dir)
+        // Uses the `_from_env!` sibling with an empty lookup/synthetic
+        // fallback so this can't fail on a machine where
+        // $XDG_RUNTIME_DIR happens to already be set.
+        let lookup = |_: &str| None::<std::ffi::OsString>;
+        let runtime_dir: PathBuf =
+            xdg_runtime_dir_from_env!(lookup, PathBuf::from("/synthetic/runtime"));
+        assert_eq!(PathBuf::from("/synthetic/runtime"), runtime_dir)
     }
 
+    #[cfg(unix)]
     #[test]
-    pub fn test_xdg_config_dir() {
-        let config_home: PathBuf = xdg_config_home!("test");
-        let expected = PathBuf::from(format!(
-            "{}/{}",
-            home_dir().unwrap().to_str().unwrap(),
-            ".config/test",
+    pub fn test_xdg_runtime_dir_checked() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        // Uses the `_from_env!` sibling with an injected lookup so the
+        // real `$XDG_RUNTIME_DIR` is never touched, avoiding a race with
+        // other tests that read it concurrently.
+        let dir = env::temp_dir().join(format!(
+            "xdg-user-macros-test-checked-{}",
+            unsafe { getuid() }
         ));
-        assert_eq!(expected, config_home)
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+        let lookup = {
+            let dir = dir.clone();
+            move |key: &str| {
+                if key == "XDG_RUNTIME_DIR" {
+                    Some(std::ffi::OsString::from(&dir))
+                } else {
+                    None
+                }
+            }
+        };
+
+        let checked: Option<PathBuf> =
+            xdg_runtime_dir_checked_from_env!(&lookup, env::temp_dir());
+        assert_eq!(Some(dir.clone()), checked);
+
+        let checked: Option<PathBuf> =
+            xdg_runtime_dir_checked_from_env!(&lookup, env::temp_dir(), "myapp.sock");
+        assert_eq!(Some(dir.join("myapp.sock")), checked);
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        let checked: Option<PathBuf> =
+            xdg_runtime_dir_checked_from_env!(&lookup, env::temp_dir());
+        assert_eq!(None, checked);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_xdg_config_dir() {
+        // Uses the `_from_env!` sibling with an empty lookup/synthetic
+        // home so this can't fail on a machine where $XDG_CONFIG_HOME
+        // happens to already be set.
+        let lookup = |_: &str| None::<std::ffi::OsString>;
+        let config_home: PathBuf =
+            xdg_config_home_from_env!(lookup, PathBuf::from("/synthetic/home"), "test");
+        assert_eq!(PathBuf::from("/synthetic/home/.config/test"), config_home)
     }
 
     #[test]
     pub fn test_xdg_cache_dir() {
-        let config_home: PathBuf = xdg_cache_home!("test");
-        let expected = PathBuf::from(format!(
-            "{}/{}",
-            home_dir().unwrap().to_str().unwrap(),
-            ".cache/test",
-        ));
-        assert_eq!(expected, config_home)
+        // Uses the `_from_env!` sibling with an empty lookup/synthetic
+        // home so this can't fail on a machine where $XDG_CACHE_HOME
+        // happens to already be set.
+        let lookup = |_: &str| None::<std::ffi::OsString>;
+        let cache_home: PathBuf =
+            xdg_cache_home_from_env!(lookup, PathBuf::from("/synthetic/home"), "test");
+        assert_eq!(PathBuf::from("/synthetic/home/.cache/test"), cache_home)
+    }
+
+    #[test]
+    pub fn test_xdg_data_dirs() {
+        let data_dirs: Vec<PathBuf> = xdg_data_dirs!("test");
+        let expected = vec![
+            PathBuf::from("/usr/local/share/test"),
+            PathBuf::from("/usr/share/test"),
+        ];
+        assert_eq!(expected, data_dirs)
+    }
+
+    #[test]
+    pub fn test_xdg_config_dirs() {
+        let config_dirs: Vec<PathBuf> = xdg_config_dirs!("test");
+        let expected = vec![PathBuf::from("/etc/xdg/test")];
+        assert_eq!(expected, config_dirs)
+    }
+
+    #[test]
+    pub fn test_xdg_data_dirs_owned_segment() {
+        // Owned, non-`Copy` segments (e.g. a `String`) must work just as
+        // well as `&str` literals, since `split_paths` yields more than one
+        // entry and each one needs the segment appended.
+        let segment: String = String::from("test");
+        let data_dirs: Vec<PathBuf> = xdg_data_dirs!(segment);
+        let expected = vec![
+            PathBuf::from("/usr/local/share/test"),
+            PathBuf::from("/usr/share/test"),
+        ];
+        assert_eq!(expected, data_dirs)
+    }
+
+    #[test]
+    pub fn test_xdg_find_config_missing() {
+        let found: Option<PathBuf> = xdg_find_config!("this-file-should-not-exist.toml");
+        assert_eq!(None, found)
+    }
+
+    #[test]
+    pub fn test_xdg_find_data_missing() {
+        let found: Option<PathBuf> = xdg_find_data!("this-file-should-not-exist.toml");
+        assert_eq!(None, found)
+    }
+
+    #[test]
+    pub fn test_xdg_find_config_respects_priority() {
+        use std::fs;
+
+        // Uses the `_from_env!` sibling with an injected lookup/home so
+        // this can't collide with the real $XDG_CONFIG_HOME/$XDG_CONFIG_DIRS.
+        let base = env::temp_dir().join("xdg-user-macros-test-find-config");
+        let _ = fs::remove_dir_all(&base);
+        let config_home = base.join("home");
+        let dir1 = base.join("dirs1");
+        let dir2 = base.join("dirs2");
+        fs::create_dir_all(&config_home).unwrap();
+        fs::create_dir_all(&dir1).unwrap();
+        fs::create_dir_all(&dir2).unwrap();
+
+        let dirs_value = format!("{}:{}", dir1.display(), dir2.display());
+        let lookup = {
+            let config_home = config_home.clone();
+            move |key: &str| match key {
+                "XDG_CONFIG_HOME" => Some(std::ffi::OsString::from(&config_home)),
+                "XDG_CONFIG_DIRS" => Some(std::ffi::OsString::from(&dirs_value)),
+                _ => None,
+            }
+        };
+
+        // Only the second $XDG_CONFIG_DIRS entry has the file: it should
+        // still be found, proving every entry is searched in order.
+        fs::write(dir2.join("app.conf"), "").unwrap();
+        let found: Option<PathBuf> =
+            xdg_find_config_from_env!(&lookup, env::temp_dir(), "app.conf");
+        assert_eq!(Some(dir2.join("app.conf")), found);
+
+        // Once $XDG_CONFIG_HOME also has it, that takes priority.
+        fs::write(config_home.join("app.conf"), "").unwrap();
+        let found: Option<PathBuf> =
+            xdg_find_config_from_env!(&lookup, env::temp_dir(), "app.conf");
+        assert_eq!(Some(config_home.join("app.conf")), found);
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    pub fn test_xdg_find_data_respects_priority() {
+        use std::fs;
+
+        // Uses the `_from_env!` sibling with an injected lookup/home so
+        // this can't collide with the real $XDG_DATA_HOME/$XDG_DATA_DIRS.
+        let base = env::temp_dir().join("xdg-user-macros-test-find-data");
+        let _ = fs::remove_dir_all(&base);
+        let data_home = base.join("home");
+        let dir1 = base.join("dirs1");
+        let dir2 = base.join("dirs2");
+        fs::create_dir_all(&data_home).unwrap();
+        fs::create_dir_all(&dir1).unwrap();
+        fs::create_dir_all(&dir2).unwrap();
+
+        let dirs_value = format!("{}:{}", dir1.display(), dir2.display());
+        let lookup = {
+            let data_home = data_home.clone();
+            move |key: &str| match key {
+                "XDG_DATA_HOME" => Some(std::ffi::OsString::from(&data_home)),
+                "XDG_DATA_DIRS" => Some(std::ffi::OsString::from(&dirs_value)),
+                _ => None,
+            }
+        };
+
+        // Only the second $XDG_DATA_DIRS entry has the file: it should
+        // still be found, proving every entry is searched in order.
+        fs::write(dir2.join("app.db"), "").unwrap();
+        let found: Option<PathBuf> = xdg_find_data_from_env!(&lookup, env::temp_dir(), "app.db");
+        assert_eq!(Some(dir2.join("app.db")), found);
+
+        // Once $XDG_DATA_HOME also has it, that takes priority.
+        fs::write(data_home.join("app.db"), "").unwrap();
+        let found: Option<PathBuf> = xdg_find_data_from_env!(&lookup, env::temp_dir(), "app.db");
+        assert_eq!(Some(data_home.join("app.db")), found);
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    pub fn test_xdg_state_home() {
+        // Uses the `_from_env!` sibling with an empty lookup/synthetic
+        // home so this can't fail on a machine where $XDG_STATE_HOME
+        // happens to already be set.
+        let lookup = |_: &str| None::<std::ffi::OsString>;
+        let state_home: PathBuf =
+            xdg_state_home_from_env!(lookup, PathBuf::from("/synthetic/home"), "test");
+        assert_eq!(PathBuf::from("/synthetic/home/.local/state/test"), state_home)
+    }
+
+    #[test]
+    pub fn test_xdg_user_dir_fallback() {
+        use std::fs;
+
+        // Uses the `_from_env!` sibling with an injected lookup/home
+        // instead of mutating the real `$XDG_CONFIG_HOME`, so this test
+        // can't race with others that read it concurrently.
+        let config_home = env::temp_dir().join("xdg-user-macros-test-user-dirs-fallback");
+        let _ = fs::remove_dir_all(&config_home);
+        fs::create_dir_all(&config_home).unwrap();
+        let home = env::temp_dir().join("xdg-user-macros-test-user-dirs-fallback-home");
+        let lookup = {
+            let config_home = config_home.clone();
+            move |key: &str| {
+                if key == "XDG_CONFIG_HOME" {
+                    Some(std::ffi::OsString::from(&config_home))
+                } else {
+                    None
+                }
+            }
+        };
+
+        let downloads: PathBuf = xdg_download_dir_from_env!(&lookup, home.clone());
+        let mut expected = home.clone();
+        expected.push("Downloads");
+        assert_eq!(expected, downloads);
+
+        fs::remove_dir_all(&config_home).unwrap();
+    }
+
+    #[test]
+    pub fn test_xdg_user_dir_parses_file() {
+        use std::fs;
+
+        // Uses the `_from_env!` sibling with an injected lookup/home
+        // instead of mutating the real `$XDG_CONFIG_HOME`, so this test
+        // can't race with others that read it concurrently.
+        let config_home = env::temp_dir().join("xdg-user-macros-test-user-dirs-parse");
+        let _ = fs::remove_dir_all(&config_home);
+        fs::create_dir_all(&config_home).unwrap();
+        fs::write(
+            config_home.join("user-dirs.dirs"),
+            "# comment\n\nXDG_DOWNLOAD_DIR=\"$HOME/Incoming\"\nXDG_MUSIC_DIR=\"/mnt/music\"\n",
+        )
+        .unwrap();
+        let home = env::temp_dir().join("xdg-user-macros-test-user-dirs-parse-home");
+        let lookup = {
+            let config_home = config_home.clone();
+            move |key: &str| {
+                if key == "XDG_CONFIG_HOME" {
+                    Some(std::ffi::OsString::from(&config_home))
+                } else {
+                    None
+                }
+            }
+        };
+
+        let downloads: PathBuf = xdg_download_dir_from_env!(&lookup, home.clone());
+        let mut expected = home.clone();
+        expected.push("Incoming");
+        assert_eq!(expected, downloads);
+
+        let music: PathBuf = xdg_music_dir_from_env!(&lookup, home.clone());
+        assert_eq!(PathBuf::from("/mnt/music"), music);
+
+        fs::remove_dir_all(&config_home).unwrap();
+    }
+
+    #[test]
+    pub fn test_xdg_config_home_from_env_with_var_set() {
+        let lookup = |key: &str| {
+            if key == "XDG_CONFIG_HOME" {
+                Some(std::ffi::OsString::from("/synthetic/config"))
+            } else {
+                None
+            }
+        };
+        let path: PathBuf = xdg_config_home_from_env!(lookup, PathBuf::from("/synthetic/home"), "test");
+        assert_eq!(PathBuf::from("/synthetic/config/test"), path)
     }
 
 }